@@ -0,0 +1,136 @@
+use std::{collections::HashMap, io::Read};
+
+use crate::{jsonerr, parse::deserialize, types::*};
+
+/// Converts a dynamically-typed [`Value`] into a concrete Rust type,
+/// walking the tree the way [`Value`] itself is laid out instead of
+/// requiring callers to write manual `match` arms.
+pub trait FromValue: Sized {
+    fn from_value(v: &Value) -> Result<Self, JSONError>;
+}
+
+impl FromValue for bool {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        match v {
+            Value::Boolean(b) => Ok(*b),
+            x => Err(jsonerr!("expected boolean, got {x:?}")),
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        v.as_f64().ok_or_else(|| jsonerr!("expected number, got {v:?}"))
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        v.as_i64().ok_or_else(|| jsonerr!("expected integer, got {v:?}"))
+    }
+}
+
+impl FromValue for u64 {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        v.as_u64().ok_or_else(|| jsonerr!("expected unsigned integer, got {v:?}"))
+    }
+}
+
+impl FromValue for String {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        match v {
+            Value::String(s) => Ok(s.clone()),
+            x => Err(jsonerr!("expected string, got {x:?}")),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        match v {
+            Value::Null => Ok(None),
+            x => Ok(Some(T::from_value(x)?)),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        match v {
+            Value::Array(arr) => arr.iter().map(T::from_value).collect(),
+            x => Err(jsonerr!("expected array, got {x:?}")),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn from_value(v: &Value) -> Result<Self, JSONError> {
+        match v {
+            Value::Object(obj) => obj
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), T::from_value(v)?)))
+                .collect(),
+            x => Err(jsonerr!("expected object, got {x:?}")),
+        }
+    }
+}
+
+/// Parses `reader` as JSON and converts the result into `T`, combining
+/// [`deserialize`] and [`FromValue::from_value`] into a single call.
+pub fn from_str<R: Read, T: FromValue>(reader: R) -> Result<T, JSONError> {
+    T::from_value(&deserialize(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_value_primitives() {
+        assert_eq!(bool::from_value(&Value::Boolean(true)), Ok(true));
+        assert_eq!(f64::from_value(&Value::Int(3)), Ok(3.0));
+        assert_eq!(i64::from_value(&Value::Int(-5)), Ok(-5));
+        assert_eq!(u64::from_value(&Value::Uint(7)), Ok(7));
+        assert_eq!(
+            String::from_value(&Value::String("hi".to_string())),
+            Ok("hi".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_value_option_and_vec() {
+        assert_eq!(Option::<i64>::from_value(&Value::Null), Ok(None));
+        assert_eq!(Option::<i64>::from_value(&Value::Int(1)), Ok(Some(1)));
+
+        let arr = Value::Array(vec![Value::Int(1), Value::Int(2)]);
+        assert_eq!(Vec::<i64>::from_value(&arr), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_from_value_hashmap() {
+        let obj = deserialize(r#"{"a": 1, "b": 2}"#.as_bytes()).unwrap();
+
+        let map = HashMap::<String, i64>::from_value(&obj).unwrap();
+
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let result: Vec<i64> = from_str(r#"[1, 2, 3]"#.as_bytes()).unwrap();
+        assert_eq!(result, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_from_value_type_mismatch() {
+        assert!(bool::from_value(&Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_from_str_rejects_lossy_float_as_i64() {
+        assert!(from_str::<_, i64>("6.022e23".as_bytes()).is_err());
+        assert!(from_str::<_, i64>("1.5".as_bytes()).is_err());
+    }
+}
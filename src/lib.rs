@@ -1,5 +1,7 @@
+mod decode;
 mod parse;
 mod types;
 
-pub use crate::parse::{deserialize, deserialize_per_line, serialize};
-pub use crate::types::{JSONError, Value};
+pub use crate::decode::{from_str, FromValue};
+pub use crate::parse::{deserialize, serialize, serialize_pretty, StreamParser};
+pub use crate::types::{JSONError, JsonEvent, Value};
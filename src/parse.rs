@@ -1,15 +1,17 @@
 use std::{
-    collections::HashMap,
     io::{Bytes, Read},
     str,
 };
 
-use crate::{jsonerr, types::*};
+use crate::types::*;
 
 struct Parser<R> {
     it: Bytes<R>,
     current: Option<u8>,
     cache: Option<Result<Token, JSONError>>,
+    byte_offset: usize,
+    line: usize,
+    column: usize,
 }
 
 impl<R: Read> Parser<R> {
@@ -20,16 +22,34 @@ impl<R: Read> Parser<R> {
             it,
             current,
             cache: None,
+            byte_offset: 0,
+            line: 1,
+            column: 1,
         }
     }
 
+    fn err(&self, message: String) -> JSONError {
+        JSONError::at(message, self.byte_offset, self.line, self.column)
+    }
+
     fn current(&self) -> Result<u8, JSONError> {
-        self.current.ok_or(jsonerr!("EOF"))
+        self.current.ok_or_else(|| self.err("EOF".to_string()))
     }
 
     fn read_byte(&mut self) -> Result<u8, JSONError> {
         let b = self.current();
         self.current = self.it.next().and_then(|x| x.ok());
+
+        if let Ok(byte) = b {
+            self.byte_offset += 1;
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+
         b
     }
 
@@ -51,6 +71,79 @@ impl<R: Read> Parser<R> {
         Ok(())
     }
 
+    /// Reads exactly 4 hex digits as a `\u` escape's 16-bit code unit.
+    fn read_hex4(&mut self) -> Result<u32, JSONError> {
+        let mut hex = Vec::new();
+
+        for _ in 0..4 {
+            hex.push(self.read_byte()?);
+        }
+
+        let digits = str::from_utf8(&hex).map_err(|e| self.err(e.to_string()))?;
+        u32::from_str_radix(digits, 16).map_err(|e| self.err(e.to_string()))
+    }
+
+    /// Reads a `\u` escape, combining a high/low surrogate pair into a
+    /// single code point when the first code unit is a high surrogate.
+    fn read_unicode_escape(&mut self) -> Result<char, JSONError> {
+        let high = self.read_hex4()?;
+
+        if (0xD800..=0xDBFF).contains(&high) {
+            match (self.read_byte()?, self.read_byte()?) {
+                (b'\\', b'u') => {}
+                _ => {
+                    return Err(self.err(format!(
+                        "lone high surrogate \\u{high:04X} must be followed by a \\u low surrogate"
+                    )))
+                }
+            }
+
+            let low = self.read_hex4()?;
+            if !(0xDC00..=0xDFFF).contains(&low) {
+                return Err(self.err(format!(
+                    "expected low surrogate after \\u{high:04X}, got \\u{low:04X}"
+                )));
+            }
+
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+            return char::from_u32(code)
+                .ok_or_else(|| self.err("invalid unicode code point".to_string()));
+        }
+
+        if (0xDC00..=0xDFFF).contains(&high) {
+            return Err(self.err(format!("unpaired low surrogate \\u{high:04X}")));
+        }
+
+        char::from_u32(high).ok_or_else(|| self.err("invalid unicode code point".to_string()))
+    }
+
+    /// Decodes a UTF-8 sequence starting with `first`, reading whatever
+    /// continuation bytes the leading byte calls for.
+    fn read_utf8_char(&mut self, first: u8) -> Result<char, JSONError> {
+        let len = if first & 0b1000_0000 == 0 {
+            1
+        } else if first & 0b1110_0000 == 0b1100_0000 {
+            2
+        } else if first & 0b1111_0000 == 0b1110_0000 {
+            3
+        } else if first & 0b1111_1000 == 0b1111_0000 {
+            4
+        } else {
+            return Err(self.err(format!("invalid UTF-8 leading byte 0x{first:02X}")));
+        };
+
+        let mut buf = vec![first];
+        for _ in 1..len {
+            buf.push(self.read_byte()?);
+        }
+
+        let decoded = str::from_utf8(&buf).map_err(|e| self.err(e.to_string()))?;
+        decoded
+            .chars()
+            .next()
+            .ok_or_else(|| self.err("invalid UTF-8 sequence".to_string()))
+    }
+
     fn read_string(&mut self) -> Result<String, JSONError> {
         let mut s = String::new();
         loop {
@@ -65,23 +158,15 @@ impl<R: Read> Parser<R> {
                     b'n' => s.push('\n'),
                     b'r' => s.push('\r'),
                     b't' => s.push('\t'),
-                    b'u' => {
-                        let mut hex = Vec::new();
-
-                        for _ in 0..4 {
-                            hex.push(self.read_byte()?);
-                        }
-
-                        let code = u32::from_str_radix(str::from_utf8(&hex)?, 16)?;
-                        let ch =
-                            char::from_u32(code).ok_or(jsonerr!("invalid unicode code point"))?;
-
-                        s.push(ch);
+                    b'u' => s.push(self.read_unicode_escape()?),
+                    b => {
+                        return Err(self.err(format!(
+                            "expected \", \\, /, b, f, n, r, t or u, got {b}"
+                        )))
                     }
-                    b => return Err(jsonerr!("expected \", \\, /, b, f, n, r, t or u, got {b}")),
                 },
                 x => {
-                    s.push(x as char);
+                    s.push(self.read_utf8_char(x)?);
                 }
             }
         }
@@ -89,8 +174,8 @@ impl<R: Read> Parser<R> {
         Ok(s)
     }
 
-    fn read_object(&mut self) -> Result<HashMap<String, Value>, JSONError> {
-        let mut d = HashMap::new();
+    fn read_object(&mut self) -> Result<Object, JSONError> {
+        let mut d = Object::new();
 
         loop {
             match self.peek_token() {
@@ -104,14 +189,14 @@ impl<R: Read> Parser<R> {
 
             let k = match self.read_token()? {
                 Token::String(s) => s,
-                x => return Err(jsonerr!("expected string, got {x:?}")),
+                x => return Err(self.err(format!("expected string, got {x:?}"))),
             };
 
             match self.read_token()? {
                 Token::Colon => {
                     // ok
                 }
-                x => return Err(jsonerr!("expected colon, got {x:?}")),
+                x => return Err(self.err(format!("expected colon, got {x:?}"))),
             }
 
             let v = self.read_value()?;
@@ -123,7 +208,7 @@ impl<R: Read> Parser<R> {
                     // ok
                 }
                 Token::RBrace => break,
-                x => return Err(jsonerr!("expected comma or }}, got {x:?}")),
+                x => return Err(self.err(format!("expected comma or }}, got {x:?}"))),
             }
         }
 
@@ -147,7 +232,7 @@ impl<R: Read> Parser<R> {
             match self.read_token()? {
                 Token::Comma => {}
                 Token::RAngle => break,
-                x => return Err(jsonerr!("expected comma or ], got {x:?}")),
+                x => return Err(self.err(format!("expected comma or ], got {x:?}"))),
             }
         }
 
@@ -170,7 +255,28 @@ impl<R: Read> Parser<R> {
             b"true" => Ok(Token::Boolean(true)),
             b"false" => Ok(Token::Boolean(false)),
             b"null" => Ok(Token::Null),
-            x => Ok(Token::Number(str::from_utf8(x)?.parse()?)),
+            x => {
+                let s = str::from_utf8(x).map_err(|e| self.err(e.to_string()))?;
+
+                if s.contains(['.', 'e', 'E']) {
+                    return s
+                        .parse()
+                        .map(Token::Float)
+                        .map_err(|e: std::num::ParseFloatError| self.err(e.to_string()));
+                }
+
+                if let Ok(i) = s.parse::<i64>() {
+                    return Ok(Token::Int(i));
+                }
+
+                if let Ok(u) = s.parse::<u64>() {
+                    return Ok(Token::Uint(u));
+                }
+
+                s.parse()
+                    .map(Token::Float)
+                    .map_err(|e: std::num::ParseFloatError| self.err(e.to_string()))
+            }
         }
     }
 
@@ -208,10 +314,157 @@ impl<R: Read> Parser<R> {
             Token::LBrace => Ok(Value::Object(self.read_object()?)),
             Token::LAngle => Ok(Value::Array(self.read_array()?)),
             Token::String(s) => Ok(Value::String(s)),
-            Token::Number(n) => Ok(Value::Number(n)),
+            Token::Int(n) => Ok(Value::Int(n)),
+            Token::Uint(n) => Ok(Value::Uint(n)),
+            Token::Float(n) => Ok(Value::Float(n)),
             Token::Boolean(b) => Ok(Value::Boolean(b)),
             Token::Null => Ok(Value::Null),
-            x => Err(jsonerr!("unexpected token {x:?}")),
+            x => Err(self.err(format!("unexpected token {x:?}"))),
+        }
+    }
+}
+
+/// A pull-parser that walks a JSON document and emits a flat stream of
+/// `JsonEvent`s instead of building a `Value` tree, so huge documents can be
+/// scanned or filtered in constant memory.
+pub struct StreamParser<R> {
+    parser: Parser<R>,
+    stack: Vec<State>,
+    started: bool,
+    done: bool,
+}
+
+impl<R: Read> StreamParser<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            parser: Parser::new(reader),
+            stack: Vec::new(),
+            started: false,
+            done: false,
+        }
+    }
+
+    fn token_to_event(&mut self, tok: Token) -> Result<JsonEvent, JSONError> {
+        match tok {
+            Token::LBrace => {
+                self.stack.push(State::InObjectFirst);
+                Ok(JsonEvent::ObjectStart)
+            }
+            Token::LAngle => {
+                self.stack.push(State::InArrayFirst);
+                Ok(JsonEvent::ArrayStart)
+            }
+            Token::String(s) => Ok(JsonEvent::String(s)),
+            Token::Int(n) => Ok(JsonEvent::Number(n as f64)),
+            Token::Uint(n) => Ok(JsonEvent::Number(n as f64)),
+            Token::Float(n) => Ok(JsonEvent::Number(n)),
+            Token::Boolean(b) => Ok(JsonEvent::Boolean(b)),
+            Token::Null => Ok(JsonEvent::Null),
+            x => Err(self.parser.err(format!("unexpected token {x:?}"))),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<JsonEvent>, JSONError> {
+        match self.stack.last().copied() {
+            None if !self.started => {
+                self.started = true;
+                let tok = self.parser.read_token()?;
+                self.token_to_event(tok).map(Some)
+            }
+            None => {
+                self.done = true;
+                match self.parser.peek_token() {
+                    Err(_) => Ok(None),
+                    Ok(_) => Err(self.parser.err("trailing data after JSON value".to_string())),
+                }
+            }
+            Some(State::ExpectValue) => {
+                self.stack.pop();
+                let tok = self.parser.read_token()?;
+                self.token_to_event(tok).map(Some)
+            }
+            Some(State::ExpectKey) => {
+                self.stack.pop();
+                match self.parser.read_token()? {
+                    Token::String(s) => {
+                        match self.parser.read_token()? {
+                            Token::Colon => {}
+                            x => return Err(self.parser.err(format!("expected colon, got {x:?}"))),
+                        }
+                        self.stack.push(State::ExpectValue);
+                        Ok(Some(JsonEvent::Key(s)))
+                    }
+                    x => Err(self.parser.err(format!("expected string, got {x:?}"))),
+                }
+            }
+            Some(State::InObjectFirst) => match self.parser.peek_token() {
+                Ok(Token::RBrace) => {
+                    _ = self.parser.read_token();
+                    self.stack.pop();
+                    Ok(Some(JsonEvent::ObjectEnd))
+                }
+                Err(err) => Err(err.clone()),
+                _ => {
+                    self.stack.pop();
+                    self.stack.push(State::InObject);
+                    self.stack.push(State::ExpectKey);
+                    self.next_event()
+                }
+            },
+            Some(State::InObject) => match self.parser.read_token()? {
+                Token::Comma => {
+                    self.stack.push(State::ExpectKey);
+                    self.next_event()
+                }
+                Token::RBrace => {
+                    self.stack.pop();
+                    Ok(Some(JsonEvent::ObjectEnd))
+                }
+                x => Err(self.parser.err(format!("expected comma or }}, got {x:?}"))),
+            },
+            Some(State::InArrayFirst) => match self.parser.peek_token() {
+                Ok(Token::RAngle) => {
+                    _ = self.parser.read_token();
+                    self.stack.pop();
+                    Ok(Some(JsonEvent::ArrayEnd))
+                }
+                Err(err) => Err(err.clone()),
+                _ => {
+                    self.stack.pop();
+                    self.stack.push(State::InArray);
+                    self.stack.push(State::ExpectValue);
+                    self.next_event()
+                }
+            },
+            Some(State::InArray) => match self.parser.read_token()? {
+                Token::Comma => {
+                    self.stack.push(State::ExpectValue);
+                    self.next_event()
+                }
+                Token::RAngle => {
+                    self.stack.pop();
+                    Ok(Some(JsonEvent::ArrayEnd))
+                }
+                x => Err(self.parser.err(format!("expected comma or ], got {x:?}"))),
+            },
+        }
+    }
+}
+
+impl<R: Read> Iterator for StreamParser<R> {
+    type Item = Result<JsonEvent, JSONError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_event() {
+            Ok(Some(ev)) => Some(Ok(ev)),
+            Ok(None) => None,
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
         }
     }
 }
@@ -224,6 +477,10 @@ pub fn serialize(val: Value) -> String {
     val.to_json()
 }
 
+pub fn serialize_pretty(val: Value, indent: usize) -> String {
+    val.to_json_pretty(indent)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -248,4 +505,166 @@ mod tests {
 
         assert!(deserialize(input.as_bytes()).is_ok())
     }
+
+    #[test]
+    fn test_number_kinds() {
+        let input = r#"[9223372036854775808, -5, 1.0, 6.022e23]"#;
+
+        let val = deserialize(input.as_bytes()).unwrap();
+        match val {
+            Value::Array(arr) => {
+                assert_eq!(arr[0], Value::Uint(9223372036854775808));
+                assert_eq!(arr[1], Value::Int(-5));
+                assert_eq!(arr[2], Value::Float(1.0));
+                assert_eq!(arr[3].as_f64(), Some(6.022e23));
+            }
+            _ => panic!("expected array"),
+        }
+    }
+
+    #[test]
+    fn test_float_round_trips_through_to_json() {
+        let val = deserialize("1.0".as_bytes()).unwrap();
+        assert_eq!(val, Value::Float(1.0));
+        assert_eq!(serialize(val), "1.0");
+
+        assert_eq!(serialize(Value::Int(1)), "1");
+    }
+
+    #[test]
+    fn test_as_i64_as_u64_reject_lossy_floats() {
+        assert_eq!(Value::Float(1.5).as_i64(), None);
+        assert_eq!(Value::Float(1.5).as_u64(), None);
+        assert_eq!(Value::Float(6.022e23).as_i64(), None);
+        assert_eq!(Value::Float(6.022e23).as_u64(), None);
+        assert_eq!(Value::Float(-1.0).as_u64(), None);
+        assert_eq!(Value::Float(3.0).as_i64(), Some(3));
+        assert_eq!(Value::Float(3.0).as_u64(), Some(3));
+    }
+
+    #[test]
+    fn test_object_preserves_key_order() {
+        let input = r#"{"z": 1, "a": 2, "m": 3}"#;
+
+        let val = deserialize(input.as_bytes()).unwrap();
+        match &val {
+            Value::Object(obj) => {
+                let keys: Vec<&str> = obj.keys().map(String::as_str).collect();
+                assert_eq!(keys, vec!["z", "a", "m"]);
+            }
+            _ => panic!("expected object"),
+        }
+
+        assert_eq!(serialize(val), r#"{"z": 1, "a": 2, "m": 3}"#);
+    }
+
+    #[test]
+    fn test_object_equality_ignores_key_order() {
+        let a = deserialize(r#"{"x": 1, "y": 2}"#.as_bytes()).unwrap();
+        let b = deserialize(r#"{"y": 2, "x": 1}"#.as_bytes()).unwrap();
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_utf8_and_surrogate_pairs() {
+        let input = "\"héllo 🎉 \\uD83C\\uDF89\"";
+
+        let val = deserialize(input.as_bytes()).unwrap();
+
+        assert_eq!(val, Value::String("héllo 🎉 🎉".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_utf8_byte_sequence_is_an_error() {
+        let input = [b'"', 0xC0, 0x80, b'"'];
+
+        let err = deserialize(&input[..]).unwrap_err();
+
+        assert_ne!((err.line(), err.column(), err.byte_offset()), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_lone_surrogate_is_an_error() {
+        let input = r#""\uD800""#;
+
+        assert!(deserialize(input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_error_position() {
+        let input = "{\n  \"a\" 1\n}";
+
+        let err = deserialize(input.as_bytes()).unwrap_err();
+
+        assert_eq!(err.line(), 2);
+        assert_eq!(err.column(), 8);
+        assert_eq!(err.byte_offset(), 9);
+    }
+
+    #[test]
+    fn test_error_position_for_malformed_number() {
+        let input = "[1, 2, 1.2.3]";
+
+        let err = deserialize(input.as_bytes()).unwrap_err();
+
+        assert_eq!(err.line(), 1);
+        assert_eq!(err.column(), 13);
+        assert_eq!(err.byte_offset(), 12);
+    }
+
+    #[test]
+    fn test_error_position_for_bad_unicode_escape() {
+        let input = r#""\uZZZZ""#;
+
+        let err = deserialize(input.as_bytes()).unwrap_err();
+
+        assert_eq!(err.line(), 1);
+        assert_eq!(err.column(), 8);
+        assert_eq!(err.byte_offset(), 7);
+    }
+
+    #[test]
+    fn test_to_json_pretty() {
+        let val = deserialize(r#"{"a": [1, 2], "b": {}, "c": []}"#.as_bytes()).unwrap();
+
+        let pretty = val.to_json_pretty(2);
+
+        assert!(pretty.contains("\n  \"a\": [\n    1,\n    2\n  ]"));
+        assert!(pretty.contains("\"b\": {}"));
+        assert!(pretty.contains("\"c\": []"));
+    }
+
+    #[test]
+    fn test_stream_parser() {
+        let input = r#"{"a": [1, 2], "b": null}"#;
+
+        let events: Result<Vec<JsonEvent>, JSONError> =
+            StreamParser::new(input.as_bytes()).collect();
+
+        assert_eq!(
+            events.unwrap(),
+            vec![
+                JsonEvent::ObjectStart,
+                JsonEvent::Key("a".to_string()),
+                JsonEvent::ArrayStart,
+                JsonEvent::Number(1.0),
+                JsonEvent::Number(2.0),
+                JsonEvent::ArrayEnd,
+                JsonEvent::Key("b".to_string()),
+                JsonEvent::Null,
+                JsonEvent::ObjectEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_parser_trailing_garbage() {
+        let input = r#"1 2"#;
+
+        let events: Result<Vec<JsonEvent>, JSONError> =
+            StreamParser::new(input.as_bytes()).collect();
+
+        assert!(events.is_err());
+    }
 }
@@ -1,12 +1,66 @@
-use std::collections::HashMap;
+use std::fmt;
 
+/// A JSON parsing error, carrying the byte offset, line, and column at
+/// which it occurred so callers can point a user at the offending input.
+/// Errors raised outside the parser, such as `FromValue` type mismatches,
+/// have no input position and report line 1, column 1, byte offset 0.
 #[derive(Clone, Debug, PartialEq)]
-pub struct JSONError(pub(crate) String);
+pub struct JSONError {
+    message: String,
+    byte_offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl JSONError {
+    pub(crate) fn new(message: String) -> Self {
+        Self {
+            message,
+            byte_offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub(crate) fn at(message: String, byte_offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            message,
+            byte_offset,
+            line,
+            column,
+        }
+    }
+
+    /// The 1-based line at which the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column at which the error occurred.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The 0-based byte offset into the input at which the error occurred.
+    pub fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+}
+
+impl fmt::Display for JSONError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
 
 #[macro_export]
 macro_rules! jsonerr {
     ($($arg:tt)*) => {
-        JSONError(format!($($arg)*))
+        $crate::types::JSONError::new(format!($($arg)*))
     };
 }
 
@@ -15,7 +69,7 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     fn from(e: E) -> Self {
-        Self(e.to_string())
+        Self::new(e.to_string())
     }
 }
 
@@ -28,28 +82,160 @@ pub(crate) enum Token {
     Comma,
     Colon,
     String(String),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Boolean(bool),
+    Null,
+}
+
+/// Parser context used by `StreamParser` to decide what the next token
+/// ought to be without materializing a `Value` tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum State {
+    ExpectKey,
+    ExpectValue,
+    InArrayFirst,
+    InArray,
+    InObjectFirst,
+    InObject,
+}
+
+/// A single token of a streamed JSON document, as produced by `StreamParser`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    ObjectStart,
+    ObjectEnd,
+    ArrayStart,
+    ArrayEnd,
+    Key(String),
+    String(String),
     Number(f64),
     Boolean(bool),
     Null,
 }
 
+/// An insertion-order-preserving map, used as the backing store for
+/// `Value::Object` so that `deserialize` followed by `serialize` reproduces
+/// the original key order. Iteration order reflects insertion order, but
+/// equality is order-independent (two objects with the same keys and values
+/// in a different order still compare equal), matching the semantics JSON
+/// objects have always had in this crate.
+#[derive(Clone, Debug, Default)]
+pub struct Object(Vec<(String, Value)>);
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl Object {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Inserts `value` under `key`, overwriting any existing entry in place
+    /// so the original key order is preserved.
+    pub fn insert(&mut self, key: String, value: Value) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.0.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.0.iter().map(|(k, _)| k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     // basic types
     String(String),
-    Number(f64),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
     Boolean(bool),
     Null,
     // compund types
     Array(Vec<Value>),
-    Object(HashMap<String, Value>),
+    Object(Object),
 }
 
 impl Value {
+    /// Returns this value as an `i64`, converting from `Uint`/`Float` when
+    /// the conversion is lossless, or `None` if it isn't a number at all or
+    /// the number has a fractional part or lies outside the `i64` range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(n) => Some(*n),
+            Value::Uint(n) => i64::try_from(*n).ok(),
+            Value::Float(n) => {
+                // 2^63: i64::MAX rounds up to this as an f64, so the range
+                // check has to use a strict upper bound.
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < 9223372036854775808.0 {
+                    Some(*n as i64)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns this value as a `u64`, converting from `Int`/`Float` when
+    /// the conversion is lossless, or `None` if it isn't a number at all or
+    /// the number has a fractional part or lies outside the `u64` range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Int(n) => u64::try_from(*n).ok(),
+            Value::Uint(n) => Some(*n),
+            Value::Float(n) => {
+                // 2^64: u64::MAX rounds up to this as an f64, so the range
+                // check has to use a strict upper bound.
+                if n.fract() == 0.0 && *n >= 0.0 && *n < 18446744073709551616.0 {
+                    Some(*n as u64)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns this value as an `f64`, or `None` if it isn't a number at all.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Uint(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
     pub fn to_json(&self) -> String {
         match self {
             Value::String(s) => Self::string_repr(s),
-            Value::Number(n) => n.to_string(),
+            Value::Int(n) => n.to_string(),
+            Value::Uint(n) => n.to_string(),
+            Value::Float(n) => Self::float_repr(*n),
             Value::Boolean(b) => b.to_string(),
             Value::Null => "null".to_string(),
             Value::Array(arr) => {
@@ -66,6 +252,68 @@ impl Value {
         }
     }
 
+    /// Like [`Value::to_json`], but each element of an object or array is
+    /// put on its own line and indented by `indent * depth` spaces. Empty
+    /// objects and arrays are still rendered on a single line as `{}`/`[]`.
+    pub fn to_json_pretty(&self, indent: usize) -> String {
+        self.to_json_pretty_at(indent, 0)
+    }
+
+    fn to_json_pretty_at(&self, indent: usize, depth: usize) -> String {
+        match self {
+            Value::Array(arr) => {
+                if arr.is_empty() {
+                    return "[]".to_string();
+                }
+
+                let pad = " ".repeat(indent * (depth + 1));
+                let items: Vec<String> = arr
+                    .iter()
+                    .map(|v| format!("{pad}{}", v.to_json_pretty_at(indent, depth + 1)))
+                    .collect();
+                format!(
+                    "[\n{}\n{}]",
+                    items.join(",\n"),
+                    " ".repeat(indent * depth)
+                )
+            }
+            Value::Object(obj) => {
+                if obj.is_empty() {
+                    return "{}".to_string();
+                }
+
+                let pad = " ".repeat(indent * (depth + 1));
+                let items: Vec<String> = obj
+                    .iter()
+                    .map(|(k, v)| {
+                        format!(
+                            "{pad}{}: {}",
+                            Self::string_repr(k),
+                            v.to_json_pretty_at(indent, depth + 1)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\n{}\n{}}}",
+                    items.join(",\n"),
+                    " ".repeat(indent * depth)
+                )
+            }
+            _ => self.to_json(),
+        }
+    }
+
+    /// Renders a float the way it was parsed: unlike `f64::to_string`,
+    /// whole numbers keep a trailing `.0` so they don't read back as `Int`.
+    fn float_repr(n: f64) -> String {
+        let s = n.to_string();
+        if n.is_finite() && !s.contains(['.', 'e', 'E']) {
+            format!("{s}.0")
+        } else {
+            s
+        }
+    }
+
     fn string_repr(s: &str) -> String {
         let mut buf = "\"".to_string();
         for c in s.chars() {